@@ -0,0 +1,174 @@
+//! Fixed-point decimal amounts.
+//!
+//! The ledger only ever needs four decimal places of precision, so instead
+//! of `f64` (and the `format!("{:.4}", amount).parse().unwrap()` round trip
+//! that used to paper over its rounding drift) we scale every amount into an
+//! `i64` and do all arithmetic in integer space.
+
+use std::fmt;
+use std::str::FromStr;
+
+use serde::{de::Error as DeError, Deserialize, Deserializer, Serialize, Serializer};
+
+/// Number of decimal places an `Amount` keeps.
+const DECIMALS: u32 = 4;
+/// Scaling factor applied to turn a decimal amount into its integer representation.
+const SCALE: i64 = 10_000; // 10^DECIMALS
+
+/// A monetary amount stored as a fixed-point number with four decimal places.
+///
+/// Internally this is an `i64` scaled by [`SCALE`], so additions and
+/// subtractions are exact integer operations instead of lossy `f64` math.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default)]
+pub struct Amount(i64);
+
+impl Amount {
+    /// The zero amount.
+    pub const ZERO: Amount = Amount(0);
+}
+
+impl std::ops::Add for Amount {
+    type Output = Amount;
+    fn add(self, rhs: Amount) -> Amount {
+        Amount(self.0 + rhs.0)
+    }
+}
+
+impl std::ops::Sub for Amount {
+    type Output = Amount;
+    fn sub(self, rhs: Amount) -> Amount {
+        Amount(self.0 - rhs.0)
+    }
+}
+
+impl std::ops::AddAssign for Amount {
+    fn add_assign(&mut self, rhs: Amount) {
+        self.0 += rhs.0;
+    }
+}
+
+impl std::ops::SubAssign for Amount {
+    fn sub_assign(&mut self, rhs: Amount) {
+        self.0 -= rhs.0;
+    }
+}
+
+/// Error returned when a string cannot be parsed as an [`Amount`].
+#[derive(Debug)]
+pub struct ParseAmountError(String);
+
+impl fmt::Display for ParseAmountError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid amount \"{}\"", self.0)
+    }
+}
+
+impl std::error::Error for ParseAmountError {}
+
+impl FromStr for Amount {
+    type Err = ParseAmountError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || ParseAmountError(s.to_string());
+
+        let s = s.trim();
+        let (negative, unsigned) = match s.strip_prefix('-') {
+            Some(rest) => (true, rest),
+            None => (false, s),
+        };
+        if unsigned.is_empty() {
+            return Err(invalid());
+        }
+
+        let mut parts = unsigned.splitn(2, '.');
+        let whole_part = parts.next().unwrap();
+        let frac_part = parts.next().unwrap_or("");
+        if !whole_part.bytes().all(|b| b.is_ascii_digit())
+            || !frac_part.bytes().all(|b| b.is_ascii_digit())
+        {
+            return Err(invalid());
+        }
+
+        let mut whole: i64 = if whole_part.is_empty() {
+            0
+        } else {
+            whole_part.parse().map_err(|_| invalid())?
+        };
+
+        // Keep `DECIMALS` digits of the fraction, rounding based on the
+        // first dropped digit (half away from zero).
+        let frac_digits: Vec<i64> = frac_part.bytes().map(|b| (b - b'0') as i64).collect();
+        let mut scaled_frac: i64 = 0;
+        for i in 0..DECIMALS as usize {
+            scaled_frac = scaled_frac * 10 + frac_digits.get(i).copied().unwrap_or(0);
+        }
+        if frac_digits.get(DECIMALS as usize).copied().unwrap_or(0) >= 5 {
+            scaled_frac += 1;
+        }
+
+        if scaled_frac == SCALE {
+            // Rounding the fraction carried it into a whole unit.
+            scaled_frac = 0;
+            whole += 1;
+        }
+
+        let mut value = whole * SCALE + scaled_frac;
+        if negative {
+            value = -value;
+        }
+        Ok(Amount(value))
+    }
+}
+
+impl fmt::Display for Amount {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let negative = self.0 < 0;
+        let magnitude = self.0.unsigned_abs();
+        let whole = magnitude / SCALE as u64;
+        let frac = magnitude % SCALE as u64;
+        if negative {
+            write!(f, "-")?;
+        }
+        write!(f, "{}.{:04}", whole, frac)
+    }
+}
+
+impl<'de> Deserialize<'de> for Amount {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let raw = String::deserialize(deserializer)?;
+        raw.trim().parse::<Amount>().map_err(DeError::custom)
+    }
+}
+
+impl Serialize for Amount {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_str(&self.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_and_rounds_to_four_decimals() {
+        assert_eq!("1.0000001".parse::<Amount>().unwrap(), Amount(10_000));
+        assert_eq!("2.0".parse::<Amount>().unwrap(), Amount(20_000));
+        assert_eq!("0.5".parse::<Amount>().unwrap(), Amount(5_000));
+        assert_eq!("-1.5".parse::<Amount>().unwrap(), Amount(-15_000));
+        assert_eq!("0.99995".parse::<Amount>().unwrap(), Amount(10_000));
+    }
+
+    #[test]
+    fn formats_with_four_decimals() {
+        assert_eq!(Amount(10_000).to_string(), "1.0000");
+        assert_eq!(Amount(-15_000).to_string(), "-1.5000");
+        assert_eq!(Amount::ZERO.to_string(), "0.0000");
+    }
+}
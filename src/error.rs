@@ -0,0 +1,40 @@
+//! Errors produced while parsing or applying transactions.
+
+use thiserror::Error;
+
+use crate::{ClientId, TxId};
+
+/// Everything that can go wrong with a single transaction record.
+///
+/// Variants raised while converting a raw CSV record into a [`Transaction`]
+/// (`MissingAmount`, `UnexpectedAmount`, `UnknownKind`) are fatal parse
+/// errors: the record is malformed and the caller should stop. The rest are
+/// raised from [`process_tx`](crate::process_tx) and are recoverable: the
+/// caller can log one and keep processing the remaining records.
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum LedgerError {
+    #[error("deposit/withdrawal entry without an amount")]
+    MissingAmount,
+    #[error("dispute/resolve/chargeback entry must not carry an amount")]
+    UnexpectedAmount,
+    #[error("unknown transaction type \"{0}\"")]
+    UnknownKind(String),
+    #[error("client {0} has no transaction {1}")]
+    UnknownTx(ClientId, TxId),
+    #[error("client {0} account is locked")]
+    FrozenAccount(ClientId),
+    #[error("client {0} has insufficient available funds")]
+    NotEnoughFunds(ClientId),
+    #[error("transaction {0} cannot be disputed: amount exceeds available funds")]
+    DisputeExceedsAvailable(TxId),
+    #[error("transaction {0} is already under dispute")]
+    AlreadyDisputed(TxId),
+    #[error("transaction {0} is not currently under dispute")]
+    NotDisputed(TxId),
+    #[error("transaction {0} has insufficient held funds to resolve")]
+    InsufficientHeld(TxId),
+    #[error("transaction {0} cannot be charged back: not a deposit")]
+    ChargebackNotDeposit(TxId),
+    #[error("storage backend error: {0}")]
+    Store(String),
+}
@@ -1,194 +1,240 @@
-use std::{collections::HashMap, error::Error, fs::File, process};
+mod amount;
+mod error;
+mod server;
+mod shard;
+mod store;
+mod transaction;
+
+use std::{error::Error, fs::File, process};
 
 use csv::Trim;
 use serde::{Deserialize, Serialize};
 
-type ClientId = u16;
-type TxId = u32;
+use amount::Amount;
+use error::LedgerError;
+use store::{DiskStore, MemoryStore, Store};
+use transaction::{Transaction, TxKind};
 
-/// Input format for transaction.
-#[derive(Debug, Deserialize)]
-struct Transaction {
-    #[serde(rename = "type")]
-    kind: String,
-    client: ClientId,
-    tx: TxId,
-    amount: Option<f64>,
-}
+pub(crate) type ClientId = u16;
+pub(crate) type TxId = u32;
 
 /// Output format for client data.
-#[derive(Debug, Serialize)]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
 struct Client {
     client: ClientId,
-    available: f64,
-    held: f64,
-    total: f64,
+    available: Amount,
+    held: Amount,
+    total: Amount,
     locked: bool,
 }
 
-/// Ledger keeps track of user accounts and their balances.
-type Ledger = HashMap<ClientId, Client>;
-
-/// Update the ledger based on the provided transaction.
-fn process_tx(
-    ledger: &mut Ledger,
-    tx_log: &mut HashMap<TxId, Transaction>,
-    mut tx: Transaction,
-) -> Result<(), String> {
-    // get client entry or create it if it does not exist
-    let client = match ledger.get_mut(&tx.client) {
-        Some(client) => client,
-        None => {
-            assert!(ledger
-                .insert(
-                    tx.client,
-                    Client {
-                        client: tx.client,
-                        available: 0.0,
-                        held: 0.0,
-                        total: 0.0,
-                        locked: false,
-                    }
-                )
-                .is_none());
-            ledger.get_mut(&tx.client).unwrap()
-        }
-    };
+/// Update the store based on the provided transaction.
+fn process_tx<S: Store>(store: &mut S, tx: Transaction) -> Result<(), LedgerError> {
+    let client_id = tx.client;
     let tx_id = tx.tx;
-    match tx.kind.as_str() {
-        "deposit" => {
-            let amount = match tx.amount {
-                Some(amount) => format!("{:.4}", amount).parse::<f64>().unwrap(),
-                None => return Err("deposit entry without the amount".into()),
-            };
+    let mut client = store
+        .get_account(client_id)
+        .map_err(store_error)?
+        .unwrap_or(Client {
+            client: client_id,
+            available: Amount::ZERO,
+            held: Amount::ZERO,
+            total: Amount::ZERO,
+            locked: false,
+        });
+
+    match tx.kind {
+        TxKind::Deposit(amount) => {
             if client.locked {
-                eprintln!("Cannot deposit - client account {} is locked", tx.client);
-                return Ok(());
+                return Err(LedgerError::FrozenAccount(client_id));
             }
             client.total += amount;
             client.available += amount;
-            tx.amount = Some(amount); // update it in case it was trimmed
-            tx_log.insert(tx_id, tx);
+            store.insert_tx(tx).map_err(store_error)?;
         }
-        "withdrawal" => {
-            let amount = match tx.amount {
-                Some(amount) => format!("{:.4}", amount).parse::<f64>().unwrap(),
-                None => return Err("withdrawal entry without the amount".into()),
-            };
+        TxKind::Withdrawal(amount) => {
             if client.locked {
-                eprintln!("Cannot withdraw - client account {} is locked", tx.client);
-                return Ok(());
+                return Err(LedgerError::FrozenAccount(client_id));
             }
             if client.available < amount {
-                eprintln!(
-                    "Insufficient balance for withdrawal from client account {}",
-                    tx.client
-                );
-                return Ok(());
+                return Err(LedgerError::NotEnoughFunds(client_id));
             }
             client.total -= amount;
             client.available -= amount;
-            tx.amount = Some(amount); // update it in case it was trimmed
-            tx_log.insert(tx_id, tx);
+            store.insert_tx(tx).map_err(store_error)?;
         }
-        "dispute" => {
-            let tx_context = match tx_log.get(&tx_id) {
-                Some(tx_context) => tx_context,
-                None => {
-                    eprintln!("Ignoring dispute that references unknown transaction");
-                    return Ok(());
-                }
-            };
-            let amount = tx_context.amount.unwrap();
+        TxKind::Dispute => {
+            let tx_context = store
+                .get_tx(tx_id)
+                .map_err(store_error)?
+                .ok_or(LedgerError::UnknownTx(client_id, tx_id))?;
+            if store.is_disputed(tx_id).map_err(store_error)? {
+                return Err(LedgerError::AlreadyDisputed(tx_id));
+            }
+            let amount = tx_context.kind.amount().unwrap();
             if client.available < amount {
-                eprintln!("Cannot dispute more than what is available");
-                return Ok(());
+                return Err(LedgerError::DisputeExceedsAvailable(tx_id));
             }
             client.available -= amount;
             client.held += amount;
+            store.mark_disputed(tx_id).map_err(store_error)?;
         }
-        "resolve" => {
-            let tx_context = match tx_log.get(&tx_id) {
-                Some(tx_context) => tx_context,
-                None => {
-                    eprintln!("Ignoring resolve that references unknown transaction");
-                    return Ok(());
-                }
-            };
-            let amount = tx_context.amount.unwrap();
+        TxKind::Resolve => {
+            if !store.is_disputed(tx_id).map_err(store_error)? {
+                return Err(LedgerError::NotDisputed(tx_id));
+            }
+            let tx_context = store
+                .get_tx(tx_id)
+                .map_err(store_error)?
+                .ok_or(LedgerError::UnknownTx(client_id, tx_id))?;
+            let amount = tx_context.kind.amount().unwrap();
             if client.held < amount {
-                eprintln!("Cannot resolve more than what is held");
-                return Ok(());
+                return Err(LedgerError::InsufficientHeld(tx_id));
             }
             client.held -= amount;
             client.available += amount;
+            store.clear_disputed(tx_id).map_err(store_error)?;
         }
-        "chargeback" => {
-            // remove the transaction from log to avoid double chargeback
-            let tx_context = match tx_log.remove(&tx_id) {
-                Some(tx_context) => tx_context,
-                None => {
-                    eprintln!("Ignoring chargeback that references unknown transaction");
-                    return Ok(());
-                }
-            };
-            if tx_context.kind != "deposit" {
-                tx_log.insert(tx_id, tx_context);
-                eprintln!("Ignoring chargeback acting on a different transaction than deposit");
-                return Ok(());
+        TxKind::Chargeback => {
+            if !store.is_disputed(tx_id).map_err(store_error)? {
+                return Err(LedgerError::NotDisputed(tx_id));
+            }
+            // remove the transaction from the log to avoid double chargeback
+            let tx_context = store
+                .remove_tx(tx_id)
+                .map_err(store_error)?
+                .ok_or(LedgerError::UnknownTx(client_id, tx_id))?;
+            if !matches!(tx_context.kind, TxKind::Deposit(_)) {
+                store.insert_tx(tx_context).map_err(store_error)?;
+                return Err(LedgerError::ChargebackNotDeposit(tx_id));
             }
-            let amount = tx_context.amount.unwrap();
+            let amount = tx_context.kind.amount().unwrap();
             client.locked = true;
             client.held -= amount;
             client.total -= amount;
+            store.clear_disputed(tx_id).map_err(store_error)?;
         }
-        kind => return Err(format!("Unknown transaction type \"{}\"", kind)),
     }
+
+    store.upsert_account(client).map_err(store_error)?;
     Ok(())
 }
 
-/// Parse all transactions from the input stream and build up the ledger.
-fn parse<R>(stream: R) -> Result<Ledger, Box<dyn Error>>
+fn store_error<E: std::error::Error>(e: E) -> LedgerError {
+    LedgerError::Store(e.to_string())
+}
+
+/// Parse all transactions from the input stream, applying them to `store`.
+fn parse<R, S>(stream: R, store: &mut S) -> Result<(), Box<dyn Error>>
 where
     R: std::io::Read,
+    S: Store,
 {
-    // transaction log keeps track of processed transactions
-    let mut tx_log: HashMap<TxId, Transaction> = HashMap::new();
-    // ledger
-    let mut ledger = HashMap::new();
-
     let mut rdr = csv::ReaderBuilder::new()
         .flexible(true)
         .trim(Trim::All)
         .from_reader(stream);
     for result in rdr.deserialize() {
-        let record = result?;
-        process_tx(&mut ledger, &mut tx_log, record)?;
+        let record: Transaction = result?;
+        if let Err(e) = process_tx(store, record) {
+            eprintln!("Ignoring transaction: {}", e);
+        }
     }
-    Ok(ledger)
+    Ok(())
 }
 
-/// Print all users from the ledger with balance info to the provided stream.
-fn output<W>(ledger: &Ledger, stream: W) -> Result<(), Box<dyn Error>>
+/// Print all users from the store with balance info to the provided stream.
+fn output<W, S>(store: &S, stream: W) -> Result<(), Box<dyn Error>>
 where
     W: std::io::Write,
+    S: Store,
 {
     let mut wtr = csv::Writer::from_writer(stream);
-    for (_, val) in ledger.iter() {
-        wtr.serialize(val)?;
+    for client in store.accounts()? {
+        wtr.serialize(client)?;
     }
     wtr.flush()?;
     Ok(())
 }
 
+/// Parse `file` into `store` and print the resulting account balances.
+fn run<S: Store>(file: File, store: &mut S) -> Result<(), Box<dyn Error>> {
+    parse(file, store)?;
+    output(store, std::io::stdout())
+}
+
+/// Print a usage message to stderr and exit with a failure status.
+fn usage_error(message: &str) -> ! {
+    eprintln!("{}", message);
+    eprintln!(
+        "Usage: ledger <input-file> [--disk-store <db-path>] [--shards <n>]\n       ledger --serve <addr> [--disk-store <db-path>]"
+    );
+    process::exit(1);
+}
+
 fn main() {
     // Parse arguments
     let args: Vec<String> = std::env::args().collect();
-    if args.len() != 2 {
-        eprintln!("Usage: ledger <input-file>");
-        process::exit(1);
+    let mut filename: Option<&str> = None;
+    let mut disk_store_path: Option<&str> = None;
+    let mut shards: Option<usize> = None;
+    let mut serve_addr: Option<&str> = None;
+
+    let mut i = 1;
+    while i < args.len() {
+        match args[i].as_str() {
+            "--disk-store" => {
+                i += 1;
+                disk_store_path = Some(
+                    args.get(i)
+                        .unwrap_or_else(|| usage_error("--disk-store requires a path")),
+                );
+            }
+            "--shards" => {
+                i += 1;
+                let value = args
+                    .get(i)
+                    .unwrap_or_else(|| usage_error("--shards requires a count"));
+                shards = Some(match value.parse() {
+                    Ok(n) if n > 0 => n,
+                    _ => usage_error("--shards expects a positive integer"),
+                });
+            }
+            "--serve" => {
+                i += 1;
+                serve_addr = Some(
+                    args.get(i)
+                        .unwrap_or_else(|| usage_error("--serve requires an address")),
+                );
+            }
+            arg if filename.is_none() => filename = Some(arg),
+            arg => usage_error(&format!("Unexpected argument \"{}\"", arg)),
+        }
+        i += 1;
     }
-    let filename: &str = &args[1];
+
+    if let Some(addr) = serve_addr {
+        if filename.is_some() || shards.is_some() {
+            usage_error("--serve cannot be combined with <input-file> or --shards");
+        }
+        let result = match disk_store_path {
+            Some(path) => match DiskStore::open(path) {
+                Ok(store) => server::serve(addr, store),
+                Err(e) => {
+                    eprintln!("Failed to open disk store at {}: {}", path, e);
+                    process::exit(1);
+                }
+            },
+            None => server::serve(addr, MemoryStore::new()),
+        };
+        if let Err(e) = result {
+            eprintln!("Server error: {}", e);
+            process::exit(1);
+        }
+        return;
+    }
+
+    let filename = filename.unwrap_or_else(|| usage_error("Missing <input-file>"));
 
     // Open the input file
     let file = match File::open(filename) {
@@ -199,20 +245,40 @@ fn main() {
         }
     };
 
-    // Parse entries from the input file stream
-    let ledger = match parse(file) {
-        Ok(ledger) => ledger,
-        Err(e) => {
-            eprintln!("Failed to parse CSV input: {}", e);
-            process::exit(1);
+    // Parse and print the ledger, using whichever store backend and
+    // concurrency mode were selected
+    let result = match (disk_store_path, shards) {
+        (None, None) => run(file, &mut MemoryStore::new()),
+        (None, Some(n)) => shard::run_sharded(
+            file,
+            (0..n).map(|_| MemoryStore::new()).collect(),
+            std::io::stdout(),
+        ),
+        (Some(path), None) => match DiskStore::open(path) {
+            Ok(mut store) => run(file, &mut store),
+            Err(e) => {
+                eprintln!("Failed to open disk store at {}: {}", path, e);
+                process::exit(1);
+            }
+        },
+        (Some(path), Some(n)) => {
+            let stores: Result<Vec<_>, _> = (0..n)
+                .map(|shard| DiskStore::open(format!("{}/shard-{}", path, shard)))
+                .collect();
+            match stores {
+                Ok(stores) => shard::run_sharded(file, stores, std::io::stdout()),
+                Err(e) => {
+                    eprintln!("Failed to open disk store at {}: {}", path, e);
+                    process::exit(1);
+                }
+            }
         }
     };
 
-    // Print the ledger
-    if let Err(e) = output(&ledger, std::io::stdout()) {
-        eprintln!("Failed to print the ledger: {}", e);
+    if let Err(e) = result {
+        eprintln!("Failed to process ledger: {}", e);
         process::exit(1);
-    };
+    }
 }
 
 #[cfg(test)]
@@ -235,47 +301,67 @@ dispute, 1, 3
 dispute, 1, 1
 chargeback, 1, 1
 ";
-        let ledger = parse(input.as_bytes())?;
-        assert_eq!(ledger.len(), 2);
-        let clnt1 = ledger.get(&1).unwrap();
-        assert_eq!(clnt1.total, 1.0);
-        assert_eq!(clnt1.available, 0.5);
-        assert_eq!(clnt1.held, 0.5);
-        assert_eq!(clnt1.locked, true);
-        let clnt2 = ledger.get(&2).unwrap();
-        assert_eq!(clnt2.total, 2.0);
-        assert_eq!(clnt2.available, 2.0);
-        assert_eq!(clnt2.held, 0.0);
-        assert_eq!(clnt2.locked, false);
+        let mut store = MemoryStore::new();
+        parse(input.as_bytes(), &mut store)?;
+        let accounts = store.accounts()?;
+        assert_eq!(accounts.len(), 2);
+        let clnt1 = accounts.iter().find(|c| c.client == 1).unwrap();
+        assert_eq!(clnt1.total, "1.0".parse().unwrap());
+        assert_eq!(clnt1.available, "0.5".parse().unwrap());
+        assert_eq!(clnt1.held, "0.5".parse().unwrap());
+        assert!(clnt1.locked);
+        let clnt2 = accounts.iter().find(|c| c.client == 2).unwrap();
+        assert_eq!(clnt2.total, "2.0".parse().unwrap());
+        assert_eq!(clnt2.available, "2.0".parse().unwrap());
+        assert_eq!(clnt2.held, Amount::ZERO);
+        assert!(!clnt2.locked);
+        Ok(())
+    }
+
+    #[test]
+    fn test_dispute_guards() -> Result<(), Box<dyn Error>> {
+        let input = "\
+type, client, tx, amount
+deposit, 1, 1, 1.0
+deposit, 1, 2, 2.0
+dispute, 1, 1
+dispute, 1, 1
+resolve, 1, 2
+chargeback, 1, 2
+resolve, 1, 1
+chargeback, 1, 1
+";
+        let mut store = MemoryStore::new();
+        parse(input.as_bytes(), &mut store)?;
+        let clnt1 = store.get_account(1)?.unwrap();
+        // the duplicate dispute, the resolve/chargeback of an undisputed tx
+        // 2, and the chargeback of the already-resolved tx 1 are all ignored
+        assert_eq!(clnt1.total, "3.0".parse().unwrap());
+        assert_eq!(clnt1.available, "3.0".parse().unwrap());
+        assert_eq!(clnt1.held, Amount::ZERO);
+        assert!(!clnt1.locked);
         Ok(())
     }
 
     #[test]
     fn test_output() -> Result<(), Box<dyn Error>> {
-        let mut ledger = HashMap::new();
-        ledger.insert(
-            1,
-            Client {
-                client: 1,
-                total: 1.5,
-                available: 1.0,
-                held: 0.5,
-                locked: true,
-            },
-        );
-        ledger.insert(
-            2,
-            Client {
-                client: 2,
-                total: 1.5,
-                available: 1.5,
-                held: 0.0,
-                locked: false,
-            },
-        );
+        let mut store = MemoryStore::new();
+        store.upsert_account(Client {
+            client: 1,
+            total: "1.5".parse().unwrap(),
+            available: "1.0".parse().unwrap(),
+            held: "0.5".parse().unwrap(),
+            locked: true,
+        })?;
+        store.upsert_account(Client {
+            client: 2,
+            total: "1.5".parse().unwrap(),
+            available: "1.5".parse().unwrap(),
+            held: Amount::ZERO,
+            locked: false,
+        })?;
         let out = Vec::new();
-        output(&ledger, out)?;
-        assert_eq!(true, true);
+        output(&store, out)?;
         Ok(())
     }
 }
@@ -0,0 +1,106 @@
+//! TCP server mode.
+//!
+//! Turns the batch tool into a long-running account service: incoming
+//! connections send line-delimited transaction records in the same
+//! `type,client,tx,amount` CSV shape the batch mode reads, each fed into
+//! [`process_tx`](crate::process_tx) against a store shared across
+//! connections. Sending the line `DUMP` writes the current account
+//! snapshot back over that connection using the existing
+//! [`output`](crate::output), in the same `Client` CSV format the batch
+//! mode prints on exit.
+
+use std::{
+    error::Error,
+    io::{BufRead, BufReader, Write},
+    net::{TcpListener, TcpStream},
+    sync::{Arc, Mutex},
+    thread,
+};
+
+use crate::store::Store;
+use crate::transaction::Transaction;
+use crate::{output, process_tx};
+
+/// Listen on `addr`, applying incoming transactions to a shared `store`
+/// until the process is terminated.
+pub fn serve<S>(addr: &str, store: S) -> Result<(), Box<dyn Error>>
+where
+    S: Store + Send + 'static,
+{
+    let listener = TcpListener::bind(addr)?;
+    let store = Arc::new(Mutex::new(store));
+    eprintln!("Listening on {}", addr);
+    for stream in listener.incoming() {
+        let stream = stream?;
+        let store = Arc::clone(&store);
+        thread::spawn(move || {
+            if let Err(e) = handle_connection(stream, &store) {
+                eprintln!("Connection error: {}", e);
+            }
+        });
+    }
+    Ok(())
+}
+
+/// Handle one client connection: apply each transaction line to `store`,
+/// replying with a snapshot dump on `DUMP` and an error line on failure.
+fn handle_connection<S>(stream: TcpStream, store: &Mutex<S>) -> Result<(), Box<dyn Error>>
+where
+    S: Store,
+{
+    let mut writer = stream.try_clone()?;
+    let reader = BufReader::new(stream);
+    for line in reader.lines() {
+        let line = line?;
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        if line.eq_ignore_ascii_case("dump") {
+            output(&*store.lock().unwrap(), &mut writer)?;
+            continue;
+        }
+        match parse_record(line) {
+            Ok(record) => {
+                if let Err(e) = process_tx(&mut *store.lock().unwrap(), record) {
+                    writeln!(writer, "error: {}", e)?;
+                }
+            }
+            Err(e) => writeln!(writer, "error: {}", e)?,
+        }
+    }
+    Ok(())
+}
+
+/// Parse a single `type,client,tx,amount` line, the same record shape the
+/// batch mode reads from a whole file, just without a header row.
+fn parse_record(line: &str) -> Result<Transaction, Box<dyn Error>> {
+    let mut rdr = csv::ReaderBuilder::new()
+        .has_headers(false)
+        .flexible(true)
+        .trim(csv::Trim::All)
+        .from_reader(line.as_bytes());
+    match rdr.deserialize().next() {
+        Some(result) => Ok(result?),
+        None => Err("empty record".into()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transaction::TxKind;
+
+    #[test]
+    fn parses_a_headerless_line() {
+        let tx = parse_record("deposit,1,7,12.5").unwrap();
+        assert_eq!(tx.client, 1);
+        assert_eq!(tx.tx, 7);
+        assert_eq!(tx.kind, TxKind::Deposit("12.5".parse().unwrap()));
+    }
+
+    #[test]
+    fn rejects_an_empty_line() {
+        assert!(parse_record("").is_err());
+    }
+}
@@ -0,0 +1,101 @@
+//! Concurrent per-client transaction processing.
+//!
+//! A transaction only ever touches a single client's account and its own
+//! entry in the transaction log, so the input stream can be sharded by
+//! `client % shard_count` across worker threads, each owning a disjoint
+//! [`Store`]. The main thread reads and parses the CSV stream and dispatches
+//! each record to its owning shard over a channel, which preserves the
+//! input order of records for any given client.
+
+use std::{error::Error, io::Write, sync::mpsc, thread};
+
+use csv::Trim;
+
+use crate::store::Store;
+use crate::transaction::Transaction;
+use crate::{process_tx, Client};
+
+/// Process `stream` by sharding transactions across `stores`, one worker
+/// thread per store, and write the merged account balances to `out`.
+pub fn run_sharded<R, W, S>(stream: R, stores: Vec<S>, out: W) -> Result<(), Box<dyn Error>>
+where
+    R: std::io::Read,
+    W: Write,
+    S: Store + Send + 'static,
+{
+    let shard_count = stores.len();
+    let mut senders = Vec::with_capacity(shard_count);
+    let mut handles = Vec::with_capacity(shard_count);
+    for mut store in stores {
+        let (tx, rx) = mpsc::channel::<Transaction>();
+        senders.push(tx);
+        handles.push(thread::spawn(move || {
+            for record in rx {
+                if let Err(e) = process_tx(&mut store, record) {
+                    eprintln!("Ignoring transaction: {}", e);
+                }
+            }
+            store
+        }));
+    }
+
+    let mut rdr = csv::ReaderBuilder::new()
+        .flexible(true)
+        .trim(Trim::All)
+        .from_reader(stream);
+    for result in rdr.deserialize() {
+        let record: Transaction = result?;
+        let shard = record.client as usize % shard_count;
+        // The only way `send` fails is if that shard's worker already
+        // stopped, which only happens if it panicked; `join` below surfaces
+        // that panic instead.
+        let _ = senders[shard].send(record);
+    }
+    drop(senders);
+
+    let mut accounts: Vec<Client> = Vec::new();
+    for handle in handles {
+        let store = handle.join().expect("shard worker thread panicked");
+        accounts.extend(store.accounts()?);
+    }
+
+    let mut wtr = csv::Writer::from_writer(out);
+    for client in accounts {
+        wtr.serialize(client)?;
+    }
+    wtr.flush()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::store::MemoryStore;
+
+    #[test]
+    fn shards_by_client_and_merges_results() -> Result<(), Box<dyn Error>> {
+        let input = "\
+type, client, tx, amount
+deposit, 1, 1, 1.0
+deposit, 2, 2, 2.0
+withdrawal, 1, 3, 0.5
+deposit, 3, 4, 3.0
+";
+        let stores: Vec<MemoryStore> = (0..4).map(|_| MemoryStore::new()).collect();
+        let mut out = Vec::new();
+        run_sharded(input.as_bytes(), stores, &mut out)?;
+
+        let output = String::from_utf8(out)?;
+        let mut lines: Vec<&str> = output.lines().skip(1).collect();
+        lines.sort_unstable();
+        assert_eq!(
+            lines,
+            vec![
+                "1,0.5000,0.0000,0.5000,false",
+                "2,2.0000,0.0000,2.0000,false",
+                "3,3.0000,0.0000,3.0000,false",
+            ]
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,188 @@
+//! Disk-backed [`Store`] for inputs with more distinct transactions than
+//! comfortably fit in memory.
+//!
+//! Accounts and the transaction log are each kept in their own `sled` tree,
+//! serialized with `bincode`; the disputed set is a third tree keyed by
+//! transaction id with an empty value.
+
+use super::Store;
+use crate::transaction::Transaction;
+use crate::{Client, ClientId, TxId};
+
+/// Errors from the disk-backed store: either the underlying storage engine
+/// or the encoding of a stored record.
+#[derive(Debug, thiserror::Error)]
+pub enum DiskStoreError {
+    #[error("storage engine error: {0}")]
+    Engine(#[from] sled::Error),
+    #[error("failed to (de)serialize a stored record: {0}")]
+    Encoding(#[from] bincode::Error),
+}
+
+/// A [`Store`] backed by an embedded on-disk key-value store.
+pub struct DiskStore {
+    accounts: sled::Tree,
+    tx_log: sled::Tree,
+    disputed: sled::Tree,
+}
+
+impl DiskStore {
+    /// Open (creating if necessary) a disk-backed store rooted at `path`.
+    pub fn open(path: impl AsRef<std::path::Path>) -> Result<Self, DiskStoreError> {
+        let db = sled::open(path)?;
+        Ok(Self {
+            accounts: db.open_tree("accounts")?,
+            tx_log: db.open_tree("tx_log")?,
+            disputed: db.open_tree("disputed")?,
+        })
+    }
+}
+
+impl Store for DiskStore {
+    type Error = DiskStoreError;
+
+    fn get_account(&self, client: ClientId) -> Result<Option<Client>, Self::Error> {
+        match self.accounts.get(client.to_be_bytes())? {
+            Some(bytes) => Ok(Some(bincode::deserialize(&bytes)?)),
+            None => Ok(None),
+        }
+    }
+
+    fn upsert_account(&mut self, account: Client) -> Result<(), Self::Error> {
+        let bytes = bincode::serialize(&account)?;
+        self.accounts.insert(account.client.to_be_bytes(), bytes)?;
+        Ok(())
+    }
+
+    fn insert_tx(&mut self, tx: Transaction) -> Result<(), Self::Error> {
+        let bytes = bincode::serialize(&tx)?;
+        self.tx_log.insert(tx.tx.to_be_bytes(), bytes)?;
+        Ok(())
+    }
+
+    fn get_tx(&self, tx: TxId) -> Result<Option<Transaction>, Self::Error> {
+        match self.tx_log.get(tx.to_be_bytes())? {
+            Some(bytes) => Ok(Some(bincode::deserialize(&bytes)?)),
+            None => Ok(None),
+        }
+    }
+
+    fn remove_tx(&mut self, tx: TxId) -> Result<Option<Transaction>, Self::Error> {
+        match self.tx_log.remove(tx.to_be_bytes())? {
+            Some(bytes) => Ok(Some(bincode::deserialize(&bytes)?)),
+            None => Ok(None),
+        }
+    }
+
+    fn is_disputed(&self, tx: TxId) -> Result<bool, Self::Error> {
+        Ok(self.disputed.contains_key(tx.to_be_bytes())?)
+    }
+
+    fn mark_disputed(&mut self, tx: TxId) -> Result<(), Self::Error> {
+        self.disputed.insert(tx.to_be_bytes(), &[])?;
+        Ok(())
+    }
+
+    fn clear_disputed(&mut self, tx: TxId) -> Result<(), Self::Error> {
+        self.disputed.remove(tx.to_be_bytes())?;
+        Ok(())
+    }
+
+    fn accounts(&self) -> Result<Vec<Client>, Self::Error> {
+        self.accounts
+            .iter()
+            .values()
+            .map(|bytes| Ok(bincode::deserialize(&bytes?)?))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::process_tx;
+    use crate::transaction::TxKind;
+
+    /// Open a `DiskStore` rooted at a fresh temporary directory unique to
+    /// this test, so concurrent test runs don't trip over each other.
+    fn open_scratch_store(name: &str) -> DiskStore {
+        let path = std::env::temp_dir().join(format!(
+            "ledger-disk-store-test-{}-{}",
+            name,
+            std::process::id()
+        ));
+        let _ = std::fs::remove_dir_all(&path);
+        DiskStore::open(&path).unwrap()
+    }
+
+    #[test]
+    fn round_trips_tx_log_through_dispute_resolve_and_chargeback() {
+        let mut store = open_scratch_store("round_trips_tx_log");
+
+        process_tx(
+            &mut store,
+            Transaction {
+                client: 1,
+                tx: 1,
+                kind: TxKind::Deposit("1.0".parse().unwrap()),
+            },
+        )
+        .unwrap();
+        process_tx(
+            &mut store,
+            Transaction {
+                client: 1,
+                tx: 2,
+                kind: TxKind::Deposit("2.0".parse().unwrap()),
+            },
+        )
+        .unwrap();
+
+        // The dispute/resolve round trip needs `get_tx` to read back what
+        // `insert_tx` wrote, byte for byte.
+        process_tx(
+            &mut store,
+            Transaction {
+                client: 1,
+                tx: 1,
+                kind: TxKind::Dispute,
+            },
+        )
+        .unwrap();
+        process_tx(
+            &mut store,
+            Transaction {
+                client: 1,
+                tx: 1,
+                kind: TxKind::Resolve,
+            },
+        )
+        .unwrap();
+
+        // The chargeback round trip additionally goes through `remove_tx`.
+        process_tx(
+            &mut store,
+            Transaction {
+                client: 1,
+                tx: 2,
+                kind: TxKind::Dispute,
+            },
+        )
+        .unwrap();
+        process_tx(
+            &mut store,
+            Transaction {
+                client: 1,
+                tx: 2,
+                kind: TxKind::Chargeback,
+            },
+        )
+        .unwrap();
+
+        let client = store.get_account(1).unwrap().unwrap();
+        assert_eq!(client.total, "1.0".parse().unwrap());
+        assert_eq!(client.available, "1.0".parse().unwrap());
+        assert_eq!(client.held, crate::amount::Amount::ZERO);
+        assert!(client.locked);
+    }
+}
@@ -0,0 +1,67 @@
+//! In-memory [`Store`], the original `HashMap`-based behaviour.
+
+use std::collections::{HashMap, HashSet};
+use std::convert::Infallible;
+
+use super::Store;
+use crate::transaction::Transaction;
+use crate::{Client, ClientId, TxId};
+
+/// Keeps every account and transaction resident in memory for the lifetime
+/// of the process.
+#[derive(Debug, Default)]
+pub struct MemoryStore {
+    accounts: HashMap<ClientId, Client>,
+    tx_log: HashMap<TxId, Transaction>,
+    disputed: HashSet<TxId>,
+}
+
+impl MemoryStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl Store for MemoryStore {
+    type Error = Infallible;
+
+    fn get_account(&self, client: ClientId) -> Result<Option<Client>, Self::Error> {
+        Ok(self.accounts.get(&client).copied())
+    }
+
+    fn upsert_account(&mut self, account: Client) -> Result<(), Self::Error> {
+        self.accounts.insert(account.client, account);
+        Ok(())
+    }
+
+    fn insert_tx(&mut self, tx: Transaction) -> Result<(), Self::Error> {
+        self.tx_log.insert(tx.tx, tx);
+        Ok(())
+    }
+
+    fn get_tx(&self, tx: TxId) -> Result<Option<Transaction>, Self::Error> {
+        Ok(self.tx_log.get(&tx).copied())
+    }
+
+    fn remove_tx(&mut self, tx: TxId) -> Result<Option<Transaction>, Self::Error> {
+        Ok(self.tx_log.remove(&tx))
+    }
+
+    fn is_disputed(&self, tx: TxId) -> Result<bool, Self::Error> {
+        Ok(self.disputed.contains(&tx))
+    }
+
+    fn mark_disputed(&mut self, tx: TxId) -> Result<(), Self::Error> {
+        self.disputed.insert(tx);
+        Ok(())
+    }
+
+    fn clear_disputed(&mut self, tx: TxId) -> Result<(), Self::Error> {
+        self.disputed.remove(&tx);
+        Ok(())
+    }
+
+    fn accounts(&self) -> Result<Vec<Client>, Self::Error> {
+        Ok(self.accounts.values().copied().collect())
+    }
+}
@@ -0,0 +1,39 @@
+//! Pluggable storage backends for client accounts and the transaction log.
+//!
+//! `process_tx` is generic over [`Store`] so the same transaction logic can
+//! run against accounts and a transaction log held entirely in memory, or
+//! against an on-disk key-value store for inputs with more distinct
+//! transactions than comfortably fit in RAM.
+
+mod disk;
+mod memory;
+
+pub use disk::DiskStore;
+pub use memory::MemoryStore;
+
+use crate::transaction::Transaction;
+use crate::{Client, ClientId, TxId};
+
+/// Storage backend for client accounts and the transaction log.
+pub trait Store {
+    type Error: std::error::Error + 'static;
+
+    /// Look up a client's account, if it has been created yet.
+    fn get_account(&self, client: ClientId) -> Result<Option<Client>, Self::Error>;
+    /// Insert or update a client's account.
+    fn upsert_account(&mut self, account: Client) -> Result<(), Self::Error>;
+    /// Record a processed deposit/withdrawal so it can later be disputed.
+    fn insert_tx(&mut self, tx: Transaction) -> Result<(), Self::Error>;
+    /// Look up a previously recorded transaction.
+    fn get_tx(&self, tx: TxId) -> Result<Option<Transaction>, Self::Error>;
+    /// Remove a previously recorded transaction, returning it if present.
+    fn remove_tx(&mut self, tx: TxId) -> Result<Option<Transaction>, Self::Error>;
+    /// Whether `tx` is currently under dispute.
+    fn is_disputed(&self, tx: TxId) -> Result<bool, Self::Error>;
+    /// Mark `tx` as currently under dispute.
+    fn mark_disputed(&mut self, tx: TxId) -> Result<(), Self::Error>;
+    /// Clear the disputed flag on `tx`.
+    fn clear_disputed(&mut self, tx: TxId) -> Result<(), Self::Error>;
+    /// All client accounts, for final output.
+    fn accounts(&self) -> Result<Vec<Client>, Self::Error>;
+}
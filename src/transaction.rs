@@ -0,0 +1,97 @@
+//! Transaction parsing.
+//!
+//! A [`Transaction`] is parsed from an untyped [`RawTransaction`] CSV record
+//! via `TryFrom`, so that "deposit/withdrawal require an amount" and
+//! "dispute/resolve/chargeback must not carry an amount" are enforced once,
+//! at parse time, instead of deep inside `process_tx`.
+
+use serde::{Deserialize, Serialize};
+
+use crate::amount::Amount;
+use crate::error::LedgerError;
+use crate::{ClientId, TxId};
+
+/// Input format for a transaction, as it appears on the wire.
+#[derive(Debug, Serialize, Deserialize)]
+struct RawTransaction {
+    #[serde(rename = "type")]
+    kind: String,
+    client: ClientId,
+    tx: TxId,
+    amount: Option<Amount>,
+}
+
+/// The kind of a transaction, together with any amount it carries.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TxKind {
+    Deposit(Amount),
+    Withdrawal(Amount),
+    Dispute,
+    Resolve,
+    Chargeback,
+}
+
+impl TxKind {
+    /// The amount a deposit or withdrawal carries, `None` for every other kind.
+    pub fn amount(&self) -> Option<Amount> {
+        match self {
+            TxKind::Deposit(amount) | TxKind::Withdrawal(amount) => Some(*amount),
+            TxKind::Dispute | TxKind::Resolve | TxKind::Chargeback => None,
+        }
+    }
+}
+
+/// A single transaction record.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(try_from = "RawTransaction", into = "RawTransaction")]
+pub struct Transaction {
+    pub client: ClientId,
+    pub tx: TxId,
+    pub kind: TxKind,
+}
+
+impl TryFrom<RawTransaction> for Transaction {
+    type Error = LedgerError;
+
+    fn try_from(raw: RawTransaction) -> Result<Self, Self::Error> {
+        let kind = match (raw.kind.as_str(), raw.amount) {
+            ("deposit", Some(amount)) => TxKind::Deposit(amount),
+            ("withdrawal", Some(amount)) => TxKind::Withdrawal(amount),
+            ("deposit", None) | ("withdrawal", None) => return Err(LedgerError::MissingAmount),
+            ("dispute", None) => TxKind::Dispute,
+            ("resolve", None) => TxKind::Resolve,
+            ("chargeback", None) => TxKind::Chargeback,
+            ("dispute", Some(_)) | ("resolve", Some(_)) | ("chargeback", Some(_)) => {
+                return Err(LedgerError::UnexpectedAmount)
+            }
+            (other, _) => return Err(LedgerError::UnknownKind(other.to_string())),
+        };
+        Ok(Transaction {
+            client: raw.client,
+            tx: raw.tx,
+            kind,
+        })
+    }
+}
+
+/// Serialize a [`Transaction`] through the same `RawTransaction` shape it is
+/// parsed from, so that encodings round-trip (notably through `bincode` in
+/// [`DiskStore`](crate::store::DiskStore), which has no CSV header to guide
+/// it back to the right `TxKind` variant).
+impl From<Transaction> for RawTransaction {
+    fn from(tx: Transaction) -> Self {
+        let kind = match tx.kind {
+            TxKind::Deposit(_) => "deposit",
+            TxKind::Withdrawal(_) => "withdrawal",
+            TxKind::Dispute => "dispute",
+            TxKind::Resolve => "resolve",
+            TxKind::Chargeback => "chargeback",
+        };
+        RawTransaction {
+            kind: kind.to_string(),
+            client: tx.client,
+            tx: tx.tx,
+            amount: tx.kind.amount(),
+        }
+    }
+}